@@ -0,0 +1,338 @@
+//! Implementations of the `Connection`/`SignedConnection` traits backed by `aleph_client`.
+
+use aleph_client::{
+    contract::ContractsApi,
+    pallets::contract::{ContractCallArgs, ContractsUserApi},
+    sp_weights::weight_v2::Weight as AlephWeight,
+    AccountId as AlephAccountId, AsConnection, SignedConnectionApi, TxInfo as AlephTxInfo,
+};
+use futures::{stream::BoxStream, StreamExt};
+use ink_primitives::{AccountId, MessageResult};
+use pallet_contracts_primitives::{ExecReturnValue, ReturnFlags};
+use sp_runtime::DispatchError;
+use subxt::Metadata;
+
+use crate::{
+    Connection, ContractError, ContractEvent, ContractEvents, DryRunResult, ExecCall,
+    GasEstimationError, InstantiateCall, ModuleError, ReadCall, SignedConnection, Weight,
+};
+
+/// Lets [`crate::util::exec_with_estimated_gas`] recognise an out-of-gas failure produced by this
+/// backend and retry it with a fresh estimate.
+impl GasEstimationError for anyhow::Error {
+    fn is_out_of_gas(&self) -> bool {
+        matches!(
+            self.downcast_ref::<ContractError>(),
+            Some(ContractError::Module(ModuleError::OutOfGas))
+        )
+    }
+}
+
+fn to_aleph_account_id(account_id: AccountId) -> AlephAccountId {
+    AlephAccountId::from(Into::<[u8; 32]>::into(account_id))
+}
+
+fn from_weight(weight: AlephWeight) -> Weight {
+    Weight {
+        ref_time: weight.ref_time(),
+        proof_size: weight.proof_size(),
+    }
+}
+
+fn to_aleph_weight(weight: Option<Weight>) -> AlephWeight {
+    weight
+        .map(|w| AlephWeight::from_parts(w.ref_time, w.proof_size))
+        .unwrap_or_default()
+}
+
+fn decode_exec_result<T: scale::Decode>(result: ExecReturnValue) -> Result<T, ContractError> {
+    use scale::Decode as _;
+
+    if result.flags.contains(ReturnFlags::REVERT) {
+        return Err(ContractError::Revert(result.data));
+    }
+
+    match MessageResult::<T>::decode(&mut result.data.as_slice()) {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(lang_error)) => Err(ContractError::Lang(lang_error.into())),
+        // The message claims to have completed normally, but its return data isn't even a
+        // well-formed `MessageResult` - treat the raw bytes as an (undeclared) revert.
+        Err(_) => Err(ContractError::Revert(result.data)),
+    }
+}
+
+#[cfg(test)]
+mod decode_exec_result_tests {
+    use ink_primitives::LangError;
+    use scale::Encode as _;
+
+    use super::*;
+
+    #[test]
+    fn returns_the_revert_data_untouched_when_the_revert_flag_is_set() {
+        let result = ExecReturnValue {
+            flags: ReturnFlags::REVERT,
+            data: vec![1, 2, 3],
+        };
+
+        assert_eq!(
+            decode_exec_result::<u32>(result),
+            Err(ContractError::Revert(vec![1, 2, 3]))
+        );
+    }
+
+    #[test]
+    fn decodes_a_successful_message_result() {
+        let result = ExecReturnValue {
+            flags: ReturnFlags::empty(),
+            data: MessageResult::<u32>::Ok(42).encode(),
+        };
+
+        assert_eq!(decode_exec_result::<u32>(result), Ok(42));
+    }
+
+    #[test]
+    fn decodes_a_lang_error_returned_without_reverting() {
+        let result = ExecReturnValue {
+            flags: ReturnFlags::empty(),
+            data: MessageResult::<u32>::Err(LangError::CouldNotReadInput).encode(),
+        };
+
+        assert_eq!(
+            decode_exec_result::<u32>(result),
+            Err(ContractError::Lang(LangError::CouldNotReadInput.into()))
+        );
+    }
+
+    #[test]
+    fn treats_data_that_isnt_even_a_well_formed_message_result_as_a_revert() {
+        // Too short to be a valid `MessageResult<u32>` (needs at least the 1-byte Ok/Err
+        // discriminant plus a 4-byte `u32`, or the 1-byte `LangError` discriminant).
+        let result = ExecReturnValue {
+            flags: ReturnFlags::empty(),
+            data: vec![0xff],
+        };
+
+        assert_eq!(
+            decode_exec_result::<u32>(result),
+            Err(ContractError::Revert(vec![0xff]))
+        );
+    }
+}
+
+/// Resolves a `DispatchError::Module` to the pallet error variant it names, using `metadata`.
+///
+/// `sp_runtime::ModuleError::message` is `#[codec(skip)]` - it's only populated when a
+/// `DispatchError` is built natively in-runtime, and is always `None` once decoded off the wire,
+/// which is how every dispatch error reaches this backend (RPC dry-run results and the errors on
+/// submitted extrinsics alike). So the error name has to be resolved against chain metadata by
+/// `(pallet index, error index)` instead.
+fn map_dispatch_error(err: DispatchError, metadata: &Metadata) -> ContractError {
+    let DispatchError::Module(module_error) = err else {
+        return ContractError::Module(ModuleError::Other(format!("{err:?}")));
+    };
+
+    let error_name = metadata
+        .pallet_by_index(module_error.index)
+        .and_then(|pallet| pallet.error_variant_by_index(module_error.error[0]))
+        .map(|variant| variant.name.as_str());
+
+    match error_name {
+        Some("OutOfGas") => ContractError::Module(ModuleError::OutOfGas),
+        Some("StorageDepositLimitExhausted") => {
+            ContractError::Module(ModuleError::StorageDepositLimitExhausted)
+        }
+        Some("ContractReverted") => ContractError::Module(ModuleError::ContractReverted),
+        Some(other) => ContractError::Module(ModuleError::Other(other.to_string())),
+        None => ContractError::Module(ModuleError::Other(format!(
+            "unresolved module error: pallet index {}, error {:?}",
+            module_error.index, module_error.error
+        ))),
+    }
+}
+
+/// Converts a real signed-submission failure into the same [`ContractError`] the dry-run path
+/// produces, so [`GasEstimationError::is_out_of_gas`] can recognise an out-of-gas *submission*
+/// failure too, not just an out-of-gas dry run. `aleph_client`'s own submission error wraps the
+/// runtime's `DispatchError` somewhere in its cause chain when the extrinsic was included but
+/// failed on-chain; anything else (a connection drop, a malformed extrinsic, ...) is passed
+/// through unchanged.
+fn map_submission_error(err: anyhow::Error, metadata: &Metadata) -> anyhow::Error {
+    let dispatch_error = err
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<DispatchError>())
+        .cloned();
+
+    match dispatch_error {
+        Some(dispatch_error) => map_dispatch_error(dispatch_error, metadata).into(),
+        None => err,
+    }
+}
+
+#[async_trait::async_trait]
+impl<C: SignedConnectionApi + AsConnection + Sync> SignedConnection<AlephTxInfo, anyhow::Error>
+    for C
+{
+    async fn instantiate<T: Send + Clone + scale::Decode + From<AccountId>>(
+        &self,
+        call: InstantiateCall<T>,
+    ) -> Result<T, anyhow::Error> {
+        if call.auto_gas {
+            let mut call = call;
+            call.auto_gas = false;
+            return crate::util::instantiate_with_estimated_gas(self, call).await;
+        }
+
+        let metadata = self.as_connection().metadata();
+        let result = self
+            .instantiate_contract(
+                call.code_hash,
+                call.value,
+                to_aleph_weight(call.gas_limit),
+                call.storage_deposit_limit,
+                call.data,
+                call.salt,
+            )
+            .await
+            .map_err(|e| map_submission_error(e, &metadata))?;
+
+        Ok(AccountId::from(Into::<[u8; 32]>::into(result.account_id)).into())
+    }
+
+    async fn exec(&self, call: ExecCall) -> Result<AlephTxInfo, anyhow::Error> {
+        if call.auto_gas {
+            let mut call = call;
+            call.auto_gas = false;
+            return crate::util::exec_with_estimated_gas(self, call).await;
+        }
+
+        let metadata = self.as_connection().metadata();
+
+        self.call_contract(
+            to_aleph_account_id(call.account_id),
+            call.value,
+            to_aleph_weight(call.gas_limit),
+            call.storage_deposit_limit,
+            call.data,
+        )
+        .await
+        .map_err(|e| map_submission_error(e, &metadata))
+    }
+}
+
+#[async_trait::async_trait]
+impl<C: AsConnection + Sync> Connection<AlephTxInfo, anyhow::Error> for C {
+    async fn read<T: scale::Decode + Send>(
+        &self,
+        call: ReadCall<T>,
+    ) -> Result<T, anyhow::Error> {
+        let args = ContractCallArgs {
+            origin: self.account_id().clone(),
+            dest: to_aleph_account_id(call.account_id),
+            value: 0,
+            gas_limit: None,
+            storage_deposit_limit: None,
+            input_data: call.data,
+        };
+
+        let result = self.as_connection().call_and_get(args).await?.result?;
+
+        decode_exec_result(result).map_err(anyhow::Error::from)
+    }
+
+    async fn dry_run<T: scale::Decode + Send>(
+        &self,
+        call: ExecCall,
+    ) -> Result<DryRunResult<T>, anyhow::Error> {
+        let args = ContractCallArgs {
+            origin: self.account_id().clone(),
+            dest: to_aleph_account_id(call.account_id),
+            value: call.value,
+            gas_limit: None,
+            storage_deposit_limit: call.storage_deposit_limit,
+            input_data: call.data,
+        };
+
+        let metadata = self.as_connection().metadata();
+        let dry_run = self.as_connection().call_and_get(args).await?;
+
+        Ok(DryRunResult {
+            gas_consumed: from_weight(dry_run.gas_consumed),
+            gas_required: from_weight(dry_run.gas_required),
+            storage_deposit: dry_run.storage_deposit.charge_or_zero(),
+            result: dry_run
+                .result
+                .map_err(|e| map_dispatch_error(e, &metadata))
+                .and_then(decode_exec_result),
+        })
+    }
+
+    async fn dry_run_instantiate<T: scale::Decode + Send>(
+        &self,
+        call: InstantiateCall<T>,
+    ) -> Result<DryRunResult<T>, anyhow::Error> {
+        let metadata = self.as_connection().metadata();
+        let dry_run = self
+            .as_connection()
+            .dry_run_instantiate(
+                call.code_hash,
+                call.value,
+                call.data,
+                call.salt,
+                call.storage_deposit_limit,
+            )
+            .await?;
+
+        Ok(DryRunResult {
+            gas_consumed: from_weight(dry_run.gas_consumed),
+            gas_required: from_weight(dry_run.gas_required),
+            storage_deposit: dry_run.storage_deposit.charge_or_zero(),
+            result: dry_run
+                .result
+                .map_err(|e| map_dispatch_error(e, &metadata))
+                .and_then(|instantiate_result| decode_exec_result(instantiate_result.result)),
+        })
+    }
+
+    async fn get_contract_events(
+        &self,
+        tx_info: AlephTxInfo,
+    ) -> Result<ContractEvents, anyhow::Error> {
+        let events = self.as_connection().get_tx_events(tx_info).await?;
+
+        Ok(ContractEvents {
+            events: events
+                .contract_events()
+                .into_iter()
+                .map(|event| crate::ContractEvent {
+                    account_id: AccountId::from(Into::<[u8; 32]>::into(event.contract)),
+                    data: event.data,
+                })
+                .collect(),
+        })
+    }
+
+    async fn subscribe_contract_events(
+        &self,
+    ) -> Result<BoxStream<'static, Result<ContractEvents, anyhow::Error>>, anyhow::Error> {
+        let blocks = self.as_connection().subscribe_finalized_blocks().await?;
+
+        Ok(blocks
+            .then(|block| async move {
+                let block = block?;
+                let events = block.events().await?;
+
+                Ok(ContractEvents {
+                    events: events
+                        .contract_events()
+                        .into_iter()
+                        .map(|event| ContractEvent {
+                            account_id: AccountId::from(Into::<[u8; 32]>::into(event.contract)),
+                            data: event.data,
+                        })
+                        .collect(),
+                })
+            })
+            .boxed())
+    }
+}
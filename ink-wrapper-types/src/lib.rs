@@ -5,6 +5,7 @@ pub mod util;
 use std::marker::PhantomData;
 
 use async_trait::async_trait;
+use futures::stream::BoxStream;
 use ink_primitives::AccountId;
 
 /// Represents a call to a contract constructor.
@@ -16,6 +17,20 @@ pub struct InstantiateCall<T: Send> {
     pub data: Vec<u8>,
     /// The salt to use for the contract.
     pub salt: Vec<u8>,
+    /// The balance to transfer to the new contract, for payable constructors.
+    pub value: u128,
+    /// The maximum weight the instantiation may consume. `None` submits the instantiation with
+    /// zero weight, which will fail for any but the most trivial constructor - either set this
+    /// explicitly via [`InstantiateCall::with_gas_limit`], or obtain an estimate by dry-running
+    /// the call first with [`Connection::dry_run_instantiate`] and reading its `gas_required`.
+    pub gas_limit: Option<Weight>,
+    /// The maximum balance that may be held as storage deposit for this instantiation.
+    /// `None` means no limit is enforced by the caller.
+    pub storage_deposit_limit: Option<u128>,
+    /// Whether [`util::instantiate_with_estimated_gas`] should dry-run this call to determine
+    /// its `gas_limit` instead of requiring the caller to set one. See
+    /// [`InstantiateCall::with_auto_gas`].
+    pub auto_gas: bool,
     /// A marker for the type of contract to instantiate.
     _contract: PhantomData<T>,
 }
@@ -27,6 +42,10 @@ impl<T: Send> InstantiateCall<T> {
             code_hash,
             data,
             salt: vec![],
+            value: 0,
+            gas_limit: None,
+            storage_deposit_limit: None,
+            auto_gas: false,
             _contract: Default::default(),
         }
     }
@@ -36,6 +55,73 @@ impl<T: Send> InstantiateCall<T> {
         self.salt = salt;
         self
     }
+
+    /// Set the balance to transfer to the new contract, for payable constructors.
+    pub fn with_value(mut self, value: u128) -> Self {
+        self.value = value;
+        self
+    }
+
+    /// Set the maximum weight the instantiation may consume.
+    pub fn with_gas_limit(mut self, gas_limit: Weight) -> Self {
+        self.gas_limit = Some(gas_limit);
+        self
+    }
+
+    /// Set the maximum balance that may be held as storage deposit for this instantiation.
+    pub fn with_storage_deposit_limit(mut self, storage_deposit_limit: Option<u128>) -> Self {
+        self.storage_deposit_limit = storage_deposit_limit;
+        self
+    }
+
+    /// Opt this instantiation into automatic gas estimation: instead of submitting it directly,
+    /// pass it to [`util::instantiate_with_estimated_gas`], which will dry-run the call to
+    /// obtain `gas_required`, apply a safety margin, and retry with a fresh estimate (up to a
+    /// bounded number of attempts) if the submitted extrinsic still runs out of gas.
+    pub fn with_auto_gas(mut self) -> Self {
+        self.auto_gas = true;
+        self
+    }
+
+    /// Computes the `AccountId` that instantiating this call from `deployer` will deterministically
+    /// produce, reproducing `pallet-contracts`' `DefaultAddressGenerator`: a `blake2_256` over the
+    /// plain concatenation of the deployer, the code hash, the constructor's raw input data, and
+    /// the salt - no domain separator and no SCALE length-prefixing of the variable-length
+    /// fields, matching the node's own `AccountIdOf::as_ref().chain(..)` byte buffer.
+    ///
+    /// This lets callers reference, fund, or register the contract for event decoding ahead of
+    /// actually submitting the instantiation.
+    pub fn contract_address(&self, deployer: &AccountId) -> AccountId {
+        let mut bytes = Vec::with_capacity(32 + 32 + self.data.len() + self.salt.len());
+        bytes.extend_from_slice(deployer.as_ref());
+        bytes.extend_from_slice(&self.code_hash);
+        bytes.extend_from_slice(&self.data);
+        bytes.extend_from_slice(&self.salt);
+
+        util::blake2_256(&bytes).into()
+    }
+}
+
+#[cfg(test)]
+mod contract_address_tests {
+    use super::*;
+
+    // Known-answer vector: independently computed as
+    // `blake2b_256(deployer ++ code_hash ++ data ++ salt)` (Python `hashlib.blake2b(..,
+    // digest_size=32)` over the same concatenation), to pin down the exact byte layout this
+    // function hashes and catch any accidental reordering/prefixing regression.
+    #[test]
+    fn contract_address_matches_known_vector() {
+        let deployer = AccountId::from([1u8; 32]);
+        let call = InstantiateCall::<()>::new([2u8; 32], vec![3, 4]).with_salt(vec![5, 6, 7]);
+
+        let expected = AccountId::from([
+            78, 171, 236, 207, 74, 56, 96, 149, 208, 193, 16, 195, 136, 0, 55, 182, 127, 147, 86,
+            59, 110, 154, 79, 112, 247, 148, 99, 69, 185, 210, 181, 131,
+        ]);
+
+        assert_eq!(call.contract_address(&deployer), expected);
+    }
 }
 
 /// Represents a mutating contract call to be made.
@@ -45,15 +131,89 @@ pub struct ExecCall {
     pub account_id: AccountId,
     /// The encoded data of the call.
     pub data: Vec<u8>,
+    /// The balance to transfer to the contract, for payable messages.
+    pub value: u128,
+    /// The maximum weight the call may consume. `None` submits the call with zero weight, which
+    /// will fail for any but the most trivial contract call - either set this explicitly via
+    /// [`ExecCall::with_gas_limit`], or call [`ExecCall::with_auto_gas`] to have
+    /// [`util::exec_with_estimated_gas`] estimate and set it automatically.
+    pub gas_limit: Option<Weight>,
+    /// The maximum balance that may be held as storage deposit for this call. `None` means no
+    /// limit is enforced by the caller.
+    pub storage_deposit_limit: Option<u128>,
+    /// Whether [`util::exec_with_estimated_gas`] should dry-run this call to determine its
+    /// `gas_limit` instead of requiring the caller to set one. See [`ExecCall::with_auto_gas`].
+    pub auto_gas: bool,
 }
 
 impl ExecCall {
     /// Create a new exec call.
     pub fn new(account_id: AccountId, data: Vec<u8>) -> Self {
-        Self { account_id, data }
+        Self {
+            account_id,
+            data,
+            value: 0,
+            gas_limit: None,
+            storage_deposit_limit: None,
+            auto_gas: false,
+        }
+    }
+
+    /// Set the balance to transfer to the contract, for payable messages.
+    pub fn with_value(mut self, value: u128) -> Self {
+        self.value = value;
+        self
+    }
+
+    /// Opt this call into automatic gas estimation: instead of submitting it directly, pass it
+    /// to [`util::exec_with_estimated_gas`], which will dry-run the call to obtain
+    /// `gas_required`, apply a safety margin, and retry with a fresh estimate (up to a bounded
+    /// number of attempts) if the submitted extrinsic still runs out of gas.
+    pub fn with_auto_gas(mut self) -> Self {
+        self.auto_gas = true;
+        self
+    }
+
+    /// Set the maximum weight the call may consume.
+    pub fn with_gas_limit(mut self, gas_limit: Weight) -> Self {
+        self.gas_limit = Some(gas_limit);
+        self
+    }
+
+    /// Set the maximum balance that may be held as storage deposit for this call.
+    pub fn with_storage_deposit_limit(mut self, storage_deposit_limit: Option<u128>) -> Self {
+        self.storage_deposit_limit = storage_deposit_limit;
+        self
     }
 }
 
+/// A `pallet-contracts` weight, expressed as its two independent dimensions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Weight {
+    /// The amount of computation time that can be used for execution.
+    pub ref_time: u64,
+    /// The amount of storage proof that can be generated.
+    pub proof_size: u64,
+}
+
+/// The outcome of dry-running a contract call or instantiation.
+///
+/// A dry run is executed against the chain's state without being signed or broadcast, so it
+/// never costs fees - it's meant to let callers preview gas usage and the return value (or
+/// `LangError`) before deciding whether to submit the real transaction.
+#[derive(Debug, Clone)]
+pub struct DryRunResult<T> {
+    /// The amount of gas the call actually consumed while dry-running.
+    pub gas_consumed: Weight,
+    /// The amount of gas that would be required for the call to succeed, including any margin
+    /// the node adds on top of `gas_consumed`.
+    pub gas_required: Weight,
+    /// The balance that would be held as storage deposit for this call.
+    pub storage_deposit: u128,
+    /// The decoded return value, or the reason the call failed.
+    pub result: Result<T, ContractError>,
+}
+
 /// Represents a read-only contract call to be made.
 #[derive(Debug, Clone)]
 pub struct ReadCall<T: scale::Decode + Send> {
@@ -90,7 +250,12 @@ pub trait SignedConnection<TxInfo, E>: Sync {
     /// Instantiate a contract with the given code hash and salt.
     ///
     /// The constructor selector and arguments are already serialized into `data`.
-    async fn instantiate<T: Send + From<AccountId>>(
+    ///
+    /// If `call.auto_gas` is set (see [`InstantiateCall::with_auto_gas`]), implementors MUST
+    /// submit the call through [`util::instantiate_with_estimated_gas`] instead of using
+    /// `call.gas_limit` as-is - hence the extra `Clone + scale::Decode` bounds needed to dry-run
+    /// and retry it.
+    async fn instantiate<T: Send + Clone + scale::Decode + From<AccountId>>(
         &self,
         call: InstantiateCall<T>,
     ) -> Result<T, E>;
@@ -98,6 +263,9 @@ pub trait SignedConnection<TxInfo, E>: Sync {
     /// Invoke a mutating method on the `account_id` contract.
     ///
     /// The method selector and arguments are already serialized into `data`.
+    ///
+    /// If `call.auto_gas` is set (see [`ExecCall::with_auto_gas`]), implementors MUST submit the
+    /// call through [`util::exec_with_estimated_gas`] instead of using `call.gas_limit` as-is.
     async fn exec(&self, call: ExecCall) -> Result<TxInfo, E>;
 }
 
@@ -109,8 +277,33 @@ pub trait Connection<TxInfo, E>: Sync {
     /// The method selector and arguments are already serialized into `data`.
     async fn read<T: scale::Decode + Send>(&self, call: ReadCall<T>) -> Result<T, E>;
 
+    /// Simulate a mutating call against the current chain state without signing or broadcasting
+    /// it, the way `cargo-contract`'s `--dry-run` does.
+    ///
+    /// Lets callers validate arguments and preview gas usage, storage deposit and the return
+    /// value (or `LangError`) before paying to submit the equivalent [`Connection::exec`] call.
+    async fn dry_run<T: scale::Decode + Send>(&self, call: ExecCall) -> Result<DryRunResult<T>, E>;
+
+    /// Simulate instantiating a contract without signing or broadcasting it.
+    ///
+    /// Behaves like [`Connection::dry_run`], but for [`InstantiateCall`].
+    async fn dry_run_instantiate<T: scale::Decode + Send>(
+        &self,
+        call: InstantiateCall<T>,
+    ) -> Result<DryRunResult<T>, E>;
+
     /// Fetch all events emitted by contracts in the transaction with the given `tx_info`.
     async fn get_contract_events(&self, tx_info: TxInfo) -> Result<ContractEvents, E>;
+
+    /// Subscribe to contract events emitted in newly finalized blocks, as they happen.
+    ///
+    /// Unlike [`Connection::get_contract_events`], which looks up one already-known transaction,
+    /// this follows the chain going forward - useful for indexers, bots, or test assertions that
+    /// need to react to events without polling individual transactions. Pair it with
+    /// [`ContractEvents::for_contract`] to get a typed feed for a specific contract.
+    async fn subscribe_contract_events(
+        &self,
+    ) -> Result<BoxStream<'static, Result<ContractEvents, E>>, E>;
 }
 
 /// Represents a raw event emitted by a contract.
@@ -166,3 +359,92 @@ impl std::fmt::Display for InkLangError {
 }
 
 impl std::error::Error for InkLangError {}
+
+/// Runtime dispatch errors surfaced by `pallet-contracts` that are not attributable to the
+/// contract's own logic - the call never reached (or never finished) the contract's code.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ModuleError {
+    /// The call ran out of the gas it was given.
+    OutOfGas,
+    /// The `storage_deposit_limit` set on the call was too low for the storage it needed.
+    StorageDepositLimitExhausted,
+    /// The contract reverted in a way not reported as ordinary `ExecReturnValue` data.
+    ContractReverted,
+    /// Any other dispatch error, kept as the pallet's own description.
+    Other(String),
+}
+
+impl std::fmt::Display for ModuleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModuleError::OutOfGas => write!(f, "out of gas"),
+            ModuleError::StorageDepositLimitExhausted => {
+                write!(f, "storage deposit limit exhausted")
+            }
+            ModuleError::ContractReverted => write!(f, "contract reverted"),
+            ModuleError::Other(msg) => write!(f, "{msg}"),
+        }
+    }
+}
+
+impl std::error::Error for ModuleError {}
+
+/// A trait that allows decoding a contract's own error type out of the raw bytes of a revert.
+///
+/// Analogous to [`EventSource`], but for the error type declared by a fallible message or
+/// constructor, so that [`ContractError::decode_revert`] can turn a raw trap payload back into
+/// something typed.
+pub trait ErrorSource {
+    /// The type to decode a revert's raw data into.
+    type Error: scale::Decode;
+}
+
+/// Represents why a contract call or dry-run failed, beyond the backend's own transport/RPC
+/// error.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ContractError {
+    /// The message or constructor itself returned `Err(LangError)`.
+    Lang(InkLangError),
+    /// The contract trapped or explicitly reverted. Carries the raw, still SCALE-encoded,
+    /// revert data - decode it with [`ContractError::decode_revert`] if the contract declares a
+    /// custom error type via [`ErrorSource`].
+    Revert(Vec<u8>),
+    /// `pallet-contracts` failed the call with a dispatch error, rather than the contract
+    /// returning or reverting on its own - see [`ModuleError`].
+    Module(ModuleError),
+}
+
+impl ContractError {
+    /// Attempts to decode a [`ContractError::Revert`]'s raw data as `C::Error`.
+    ///
+    /// Returns `None` if this isn't a `Revert`, or if the data doesn't decode as `C::Error`.
+    pub fn decode_revert<C: ErrorSource>(&self) -> Option<C::Error> {
+        use scale::Decode as _;
+
+        match self {
+            ContractError::Revert(data) => C::Error::decode(&mut data.as_slice()).ok(),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ContractError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ContractError::Lang(e) => write!(f, "{e}"),
+            ContractError::Revert(data) => write!(f, "contract reverted: {data:?}"),
+            ContractError::Module(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for ContractError {}
+
+/// Implemented by a backend's error type so that generic helpers - currently
+/// [`util::exec_with_estimated_gas`] and [`util::instantiate_with_estimated_gas`] - can recognise an out-of-gas/weight failure and retry it
+/// with a fresh estimate, instead of bubbling it straight up.
+pub trait GasEstimationError {
+    /// Returns `true` if this error indicates the call ran out of gas or exceeded its weight
+    /// limit, and could plausibly succeed with a higher `gas_limit`.
+    fn is_out_of_gas(&self) -> bool;
+}
@@ -0,0 +1,342 @@
+//! Free-standing helpers that don't belong to any particular trait or call type.
+
+use blake2::{digest::consts::U32, Blake2b, Digest};
+
+use ink_primitives::AccountId;
+
+use crate::{
+    Connection, ContractError, ExecCall, GasEstimationError, InstantiateCall, ModuleError,
+    SignedConnection, Weight,
+};
+
+/// Hashes `data` with BLAKE2b, truncated to a 256-bit (32-byte) digest.
+///
+/// This is the hash `pallet-contracts` itself uses wherever a `blake2_256` is called for, e.g.
+/// when deriving a contract's address.
+pub fn blake2_256(data: &[u8]) -> [u8; 32] {
+    let mut hasher = Blake2b::<U32>::new();
+    hasher.update(data);
+    hasher.finalize().into()
+}
+
+/// How many attempts [`exec_with_estimated_gas`] will make in total - the first submission plus
+/// any re-estimated retries after an out-of-gas/weight error - before giving up and returning
+/// that error.
+pub const DEFAULT_GAS_ESTIMATION_ATTEMPTS: u8 = 3;
+
+/// The multiplier applied to a dry-run's `gas_required` before it's used as the real call's
+/// `gas_limit`, to leave headroom for the weight actually consumed on-chain varying a little
+/// from the dry run.
+pub const DEFAULT_GAS_SAFETY_MARGIN: f64 = 1.2;
+
+fn apply_margin(gas_required: Weight, margin: f64) -> Weight {
+    Weight {
+        ref_time: (gas_required.ref_time as f64 * margin) as u64,
+        proof_size: (gas_required.proof_size as f64 * margin) as u64,
+    }
+}
+
+#[cfg(test)]
+mod apply_margin_tests {
+    use super::*;
+
+    #[test]
+    fn scales_both_components_by_the_margin() {
+        let gas_required = Weight {
+            ref_time: 1_000,
+            proof_size: 2_000,
+        };
+
+        let scaled = apply_margin(gas_required, DEFAULT_GAS_SAFETY_MARGIN);
+
+        assert_eq!(
+            scaled,
+            Weight {
+                ref_time: 1_200,
+                proof_size: 2_400,
+            }
+        );
+    }
+
+    #[test]
+    fn a_margin_of_one_is_a_no_op() {
+        let gas_required = Weight {
+            ref_time: 1_234,
+            proof_size: 567,
+        };
+
+        assert_eq!(apply_margin(gas_required, 1.0), gas_required);
+    }
+}
+
+/// Submits `call` against `conn`, automatically estimating its `gas_limit`.
+///
+/// The call is first dry-run to obtain `gas_required`, which is scaled by
+/// `DEFAULT_GAS_SAFETY_MARGIN` and used as the `gas_limit` for the real, signed submission. If
+/// that submission still fails with an out-of-gas/weight error, the call is re-estimated and
+/// retried, for at most `DEFAULT_GAS_ESTIMATION_ATTEMPTS` attempts in total, so that a transient underestimate
+/// self-heals instead of bubbling up to the caller.
+///
+/// This is what [`crate::ExecCall::with_auto_gas`] opts a call into - most callers should reach
+/// for that instead of calling this directly.
+pub async fn exec_with_estimated_gas<C, TxInfo, E>(
+    conn: &C,
+    mut call: ExecCall,
+) -> Result<TxInfo, E>
+where
+    C: Connection<TxInfo, E> + SignedConnection<TxInfo, E>,
+    E: GasEstimationError + From<ContractError>,
+{
+    let mut last_err = None;
+
+    for _ in 0..DEFAULT_GAS_ESTIMATION_ATTEMPTS {
+        let dry_run = conn.dry_run::<()>(call.clone()).await?;
+
+        // The dry run's own result already proves whether the call would succeed. If it failed
+        // for a reason that isn't gas-related (a `Lang`/`Revert` error, say), submitting the real
+        // extrinsic would just pay fees to watch it fail again the same way - surface it now.
+        if let Err(contract_error) = &dry_run.result {
+            if !matches!(contract_error, ContractError::Module(ModuleError::OutOfGas)) {
+                return Err(E::from(contract_error.clone()));
+            }
+        }
+
+        call.gas_limit = Some(apply_margin(dry_run.gas_required, DEFAULT_GAS_SAFETY_MARGIN));
+
+        match conn.exec(call.clone()).await {
+            Ok(tx_info) => return Ok(tx_info),
+            Err(e) if e.is_out_of_gas() => last_err = Some(e),
+            Err(e) => return Err(e),
+        }
+    }
+
+    Err(last_err.expect("at least one attempt is always made"))
+}
+
+#[cfg(test)]
+mod exec_with_estimated_gas_tests {
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::{ContractEvents, DryRunResult, ReadCall};
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct FakeError(ContractError);
+
+    impl From<ContractError> for FakeError {
+        fn from(err: ContractError) -> Self {
+            FakeError(err)
+        }
+    }
+
+    impl GasEstimationError for FakeError {
+        fn is_out_of_gas(&self) -> bool {
+            matches!(self.0, ContractError::Module(ModuleError::OutOfGas))
+        }
+    }
+
+    /// What [`FakeConnection::dry_run`] should report on its next call, and what
+    /// [`FakeConnection::exec`] should do in response to the resulting `gas_limit`.
+    #[derive(Clone)]
+    enum Step {
+        /// The dry run fails for a reason that isn't gas-related - `exec` should never be called.
+        DryRunFails(ContractError),
+        /// The dry run succeeds; `exec` then fails with an out-of-gas error, forcing a retry.
+        ExecOutOfGas,
+        /// The dry run succeeds; `exec` then succeeds too.
+        ExecSucceeds,
+    }
+
+    /// A fake `Connection`/`SignedConnection` pair that plays back a fixed script of dry-run and
+    /// exec outcomes, so the retry/short-circuit logic in [`exec_with_estimated_gas`] can be
+    /// exercised without a real chain connection. `dry_run` only looks at the current step;
+    /// `exec` consumes it.
+    struct FakeConnection {
+        script: Mutex<Vec<Step>>,
+        exec_calls: Mutex<u32>,
+    }
+
+    impl FakeConnection {
+        fn new(script: Vec<Step>) -> Self {
+            Self {
+                script: Mutex::new(script),
+                exec_calls: Mutex::new(0),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Connection<(), FakeError> for FakeConnection {
+        async fn read<T: scale::Decode + Send>(
+            &self,
+            _call: ReadCall<T>,
+        ) -> Result<T, FakeError> {
+            unimplemented!("not exercised by these tests")
+        }
+
+        async fn dry_run<T: scale::Decode + Send>(
+            &self,
+            _call: ExecCall,
+        ) -> Result<DryRunResult<T>, FakeError> {
+            let step = self.script.lock().unwrap()[0].clone();
+
+            let result = match step {
+                Step::DryRunFails(err) => Err(err),
+                Step::ExecOutOfGas | Step::ExecSucceeds => {
+                    // The only dry run `exec_with_estimated_gas` ever performs is `dry_run::<()>`,
+                    // so `T` is always `()` here.
+                    Ok(T::decode(&mut [].as_slice()).expect("T is always () in these tests"))
+                }
+            };
+
+            Ok(DryRunResult {
+                gas_consumed: Weight::default(),
+                gas_required: Weight::default(),
+                storage_deposit: 0,
+                result,
+            })
+        }
+
+        async fn dry_run_instantiate<T: scale::Decode + Send>(
+            &self,
+            _call: InstantiateCall<T>,
+        ) -> Result<DryRunResult<T>, FakeError> {
+            unimplemented!("not exercised by these tests")
+        }
+
+        async fn get_contract_events(&self, _tx_info: ()) -> Result<ContractEvents, FakeError> {
+            unimplemented!("not exercised by these tests")
+        }
+
+        async fn subscribe_contract_events(
+            &self,
+        ) -> Result<
+            futures::stream::BoxStream<'static, Result<ContractEvents, FakeError>>,
+            FakeError,
+        > {
+            unimplemented!("not exercised by these tests")
+        }
+    }
+
+    #[async_trait]
+    impl SignedConnection<(), FakeError> for FakeConnection {
+        async fn instantiate<T: Send + Clone + scale::Decode + From<AccountId>>(
+            &self,
+            _call: InstantiateCall<T>,
+        ) -> Result<T, FakeError> {
+            unimplemented!("not exercised by these tests")
+        }
+
+        async fn exec(&self, _call: ExecCall) -> Result<(), FakeError> {
+            *self.exec_calls.lock().unwrap() += 1;
+
+            match self.script.lock().unwrap().remove(0) {
+                Step::ExecSucceeds => Ok(()),
+                Step::ExecOutOfGas => Err(FakeError(ContractError::Module(ModuleError::OutOfGas))),
+                Step::DryRunFails(_) => {
+                    panic!("exec should not be called after a non-gas dry-run failure")
+                }
+            }
+        }
+    }
+
+    fn call() -> ExecCall {
+        ExecCall::new(AccountId::from([0u8; 32]), vec![]).with_auto_gas()
+    }
+
+    #[tokio::test]
+    async fn succeeds_immediately_when_the_first_attempt_has_enough_gas() {
+        let conn = FakeConnection::new(vec![Step::ExecSucceeds]);
+
+        let result = exec_with_estimated_gas(&conn, call()).await;
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(*conn.exec_calls.lock().unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn retries_on_out_of_gas_and_eventually_succeeds() {
+        let conn = FakeConnection::new(vec![
+            Step::ExecOutOfGas,
+            Step::ExecOutOfGas,
+            Step::ExecSucceeds,
+        ]);
+
+        let result = exec_with_estimated_gas(&conn, call()).await;
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(*conn.exec_calls.lock().unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_exhausting_its_attempts() {
+        let conn = FakeConnection::new(vec![Step::ExecOutOfGas; DEFAULT_GAS_ESTIMATION_ATTEMPTS as usize]);
+
+        let result = exec_with_estimated_gas(&conn, call()).await;
+
+        assert_eq!(
+            result,
+            Err(FakeError(ContractError::Module(ModuleError::OutOfGas)))
+        );
+        assert_eq!(
+            *conn.exec_calls.lock().unwrap(),
+            DEFAULT_GAS_ESTIMATION_ATTEMPTS as u32
+        );
+    }
+
+    #[tokio::test]
+    async fn short_circuits_on_a_non_gas_dry_run_failure_without_calling_exec() {
+        let conn = FakeConnection::new(vec![Step::DryRunFails(ContractError::Revert(vec![1, 2, 3]))]);
+
+        let result = exec_with_estimated_gas(&conn, call()).await;
+
+        assert_eq!(result, Err(FakeError(ContractError::Revert(vec![1, 2, 3]))));
+        assert_eq!(*conn.exec_calls.lock().unwrap(), 0);
+    }
+}
+
+/// Instantiates `call` against `conn`, automatically estimating its `gas_limit`.
+///
+/// Mirrors [`exec_with_estimated_gas`], but for [`InstantiateCall`]: the call is first dry-run
+/// via [`Connection::dry_run_instantiate`] to obtain `gas_required`, scaled by
+/// `DEFAULT_GAS_SAFETY_MARGIN` and used as the `gas_limit` for the real, signed instantiation. If
+/// that still fails with an out-of-gas/weight error, the call is re-estimated and retried, for at
+/// most `DEFAULT_GAS_ESTIMATION_ATTEMPTS` attempts in total.
+///
+/// This is what [`crate::InstantiateCall::with_auto_gas`] opts a call into - most callers should
+/// reach for that instead of calling this directly.
+pub async fn instantiate_with_estimated_gas<C, TxInfo, E, T>(
+    conn: &C,
+    mut call: InstantiateCall<T>,
+) -> Result<T, E>
+where
+    C: Connection<TxInfo, E> + SignedConnection<TxInfo, E>,
+    T: scale::Decode + Send + Clone + From<AccountId>,
+    E: GasEstimationError + From<ContractError>,
+{
+    let mut last_err = None;
+
+    for _ in 0..DEFAULT_GAS_ESTIMATION_ATTEMPTS {
+        let dry_run = conn.dry_run_instantiate::<T>(call.clone()).await?;
+
+        // As in `exec_with_estimated_gas`: a dry-run failure that isn't gas-related will fail
+        // the real instantiation the same way, so there's no point paying to submit it.
+        if let Err(contract_error) = &dry_run.result {
+            if !matches!(contract_error, ContractError::Module(ModuleError::OutOfGas)) {
+                return Err(E::from(contract_error.clone()));
+            }
+        }
+
+        call.gas_limit = Some(apply_margin(dry_run.gas_required, DEFAULT_GAS_SAFETY_MARGIN));
+
+        match conn.instantiate(call.clone()).await {
+            Ok(result) => return Ok(result),
+            Err(e) if e.is_out_of_gas() => last_err = Some(e),
+            Err(e) => return Err(e),
+        }
+    }
+
+    Err(last_err.expect("at least one attempt is always made"))
+}